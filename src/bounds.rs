@@ -0,0 +1,289 @@
+/*
+Copyright 2022 James Forster
+
+This file is part of range_bounds_map.
+
+range_bounds_map is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+range_bounds_map is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A small newtype bodge to let us use the [`Bound`] of a
+//! [`RangeBounds`]'s `start_bound()` as a [`BTreeMap`] key.
+//!
+//! [`Bound`] doesn't implement [`Ord`] on its own (and shouldn't, there
+//! isn't one true ordering), so we wrap it here and give it the
+//! ordering that treats `Unbounded` as the smallest possible start and
+//! breaks ties between `Included(x)` and `Excluded(x)` by treating the
+//! `Included` variant as coming first.
+//!
+//! [`BTreeMap`]: std::collections::BTreeMap
+//! [`RangeBounds`]: std::ops::RangeBounds
+
+use std::cmp::Ordering;
+use std::ops::{Bound, Range, RangeInclusive};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StartBound<T>(pub(crate) Bound<T>);
+
+impl<T> PartialOrd for StartBound<T>
+where
+	T: Ord,
+{
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T> Ord for StartBound<T>
+where
+	T: Ord,
+{
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (&self.0, &other.0) {
+			(Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+			(Bound::Unbounded, _) => Ordering::Less,
+			(_, Bound::Unbounded) => Ordering::Greater,
+			(Bound::Included(a), Bound::Included(b)) => a.cmp(b),
+			(Bound::Excluded(a), Bound::Excluded(b)) => a.cmp(b),
+			(Bound::Included(a), Bound::Excluded(b)) => {
+				a.cmp(b).then(Ordering::Less)
+			}
+			(Bound::Excluded(a), Bound::Included(b)) => {
+				a.cmp(b).then(Ordering::Greater)
+			}
+		}
+	}
+}
+
+/// The complement of [`StartBound`], used when we need to compare an
+/// `end_bound()` against a [`StartBound`] (for example to test whether
+/// two ranges overlap or touch).
+///
+/// Unlike [`StartBound`], here `Unbounded` is the largest possible
+/// position, and ties between `Included(x)` and `Excluded(x)` favour
+/// `Excluded` coming first, since an excluded end at `x` finishes
+/// before an included end at `x`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EndBound<T>(pub(crate) Bound<T>);
+
+impl<T> PartialOrd for EndBound<T>
+where
+	T: Ord,
+{
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T> Ord for EndBound<T>
+where
+	T: Ord,
+{
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (&self.0, &other.0) {
+			(Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+			(Bound::Unbounded, _) => Ordering::Greater,
+			(_, Bound::Unbounded) => Ordering::Less,
+			(Bound::Included(a), Bound::Included(b)) => a.cmp(b),
+			(Bound::Excluded(a), Bound::Excluded(b)) => a.cmp(b),
+			(Bound::Included(a), Bound::Excluded(b)) => {
+				a.cmp(b).then(Ordering::Greater)
+			}
+			(Bound::Excluded(a), Bound::Included(b)) => {
+				a.cmp(b).then(Ordering::Less)
+			}
+		}
+	}
+}
+
+/// Flips an `Included` bound to the equivalent `Excluded` bound at the
+/// same point and vice versa, leaving `Unbounded` untouched.
+///
+/// This is the key trick behind [`gaps()`](crate::RangeBoundsMap::gaps):
+/// a stored key's `start_bound()` of e.g. `Included(5)` means the gap
+/// immediately before it has to end at `Excluded(5)` to avoid
+/// double-counting the point `5`, and vice versa for `end_bound()`s.
+pub(crate) fn flip_bound<T>(bound: Bound<T>) -> Bound<T> {
+	match bound {
+		Bound::Included(point) => Bound::Excluded(point),
+		Bound::Excluded(point) => Bound::Included(point),
+		Bound::Unbounded => Bound::Unbounded,
+	}
+}
+
+/// Compares an `end_bound()` against a `start_bound()` to test whether
+/// the range that owns `end` finishes strictly before the range that
+/// owns `start` begins, i.e. whether the two ranges do *not* overlap.
+pub(crate) fn ends_before_starts<T>(
+	end: &Bound<T>,
+	start: &Bound<T>,
+) -> bool
+where
+	T: Ord,
+{
+	match (end, start) {
+		(Bound::Unbounded, _) | (_, Bound::Unbounded) => false,
+		(Bound::Included(e), Bound::Included(s)) => e < s,
+		(Bound::Included(e), Bound::Excluded(s)) => e <= s,
+		(Bound::Excluded(e), Bound::Included(s)) => e <= s,
+		(Bound::Excluded(e), Bound::Excluded(s)) => e <= s,
+	}
+}
+
+/// Compares a `start_bound()` against its own range's `end_bound()` to
+/// test whether the range is back-to-front, i.e. starts strictly after
+/// it ends.
+///
+/// Used to enforce the other of [`RangeBoundsMap`](crate::RangeBoundsMap)'s
+/// two invariants: a key's `start_bound()` <= its `end_bound()`.
+pub(crate) fn starts_after_ends<T>(start: &Bound<T>, end: &Bound<T>) -> bool
+where
+	T: Ord,
+{
+	match (start, end) {
+		(Bound::Unbounded, _) | (_, Bound::Unbounded) => false,
+		(Bound::Included(s), Bound::Included(e))
+		| (Bound::Included(s), Bound::Excluded(e))
+		| (Bound::Excluded(s), Bound::Included(e))
+		| (Bound::Excluded(s), Bound::Excluded(e)) => s > e,
+	}
+}
+
+/// Compares an `end_bound()` against a `start_bound()` to test whether
+/// the range owning `end` is immediately adjacent to (touches) the
+/// range owning `start`, with no points and no gap between them.
+///
+/// For two `Included`/`Excluded` bounds sitting at the same point this
+/// is always a touch (e.g. `0..5` touches `5..10`). For two `Included`
+/// bounds there is a one-point-wide gap unless the domain is discrete,
+/// hence the `add_one` escape hatch (see [`StepLite`]) used to bridge
+/// it (e.g. `0..=4` touches `5..=9`).
+pub(crate) fn bounds_touch<T>(
+	end: &Bound<T>,
+	start: &Bound<T>,
+	add_one: &impl Fn(&T) -> T,
+) -> bool
+where
+	T: Ord,
+{
+	match (end, start) {
+		(Bound::Excluded(e), Bound::Included(s)) => e == s,
+		(Bound::Included(e), Bound::Excluded(s)) => e == s,
+		(Bound::Included(e), Bound::Included(s)) => add_one(e) == *s,
+		_ => false,
+	}
+}
+
+/// The same check as [`bounds_touch`], but phrased the other way
+/// around in terms of `sub_one`, for callers walking backwards from a
+/// `start_bound()` to the `end_bound()` of the entry before it.
+pub(crate) fn bounds_touch_backwards<T>(
+	end: &Bound<T>,
+	start: &Bound<T>,
+	sub_one: &impl Fn(&T) -> T,
+) -> bool
+where
+	T: Ord,
+{
+	match (end, start) {
+		(Bound::Excluded(e), Bound::Included(s)) => e == s,
+		(Bound::Included(e), Bound::Excluded(s)) => e == s,
+		(Bound::Included(e), Bound::Included(s)) => *e == sub_one(s),
+		_ => false,
+	}
+}
+
+/// A cheap, infallible `+1`/`-1` for discrete key types.
+///
+/// Implemented for all the standard integer types. This lets
+/// coalescing inserts (see
+/// [`RangeBoundsMap::insert_merge_touching`](crate::RangeBoundsMap::insert_merge_touching))
+/// recognise that e.g. `0..=4` and `5..=9` are adjacent even though
+/// neither range's bound is literally equal to the other's, which is
+/// something pure [`RangeBounds`](std::ops::RangeBounds) comparisons
+/// can't see. For a key type that isn't one of the standard integers,
+/// use the `*_with_step_fns` variants and pass your own `add_one`/
+/// `sub_one` functions instead of implementing this trait.
+pub trait StepLite {
+	/// Returns the next element immediately after `self`.
+	fn add_one(&self) -> Self;
+	/// Returns the element immediately before `self`.
+	fn sub_one(&self) -> Self;
+}
+
+macro_rules! impl_step_lite {
+	($($t:ty),+ $(,)?) => {
+		$(
+			impl StepLite for $t {
+				fn add_one(&self) -> Self {
+					self + 1
+				}
+				fn sub_one(&self) -> Self {
+					self - 1
+				}
+			}
+		)+
+	};
+}
+
+impl_step_lite!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Lets [`RangeBoundsMap`](crate::RangeBoundsMap)'s coalescing insert
+/// methods rebuild a key of type `Self` from the (possibly widened)
+/// start and end [`Bound`]s of a merge, without needing to know
+/// anything else about `Self`.
+///
+/// Implemented for [`Range`] and [`RangeInclusive`], the two range
+/// types that can actually result from a merge of same-shaped keys.
+pub trait RangeBoundsExt<T>: Sized {
+	/// Builds `Self` out of a start and end bound.
+	///
+	/// # Panics
+	///
+	/// Panics if `start`/`end` aren't of the bound kind `Self`
+	/// requires (e.g. building a [`Range`] out of an `Included` end
+	/// bound). This can't happen when called from the coalescing
+	/// insert methods, since they only ever widen bounds taken from
+	/// existing keys of type `Self`.
+	fn from_bounds(start: Bound<T>, end: Bound<T>) -> Self;
+}
+
+impl<T> RangeBoundsExt<T> for Range<T> {
+	fn from_bounds(start: Bound<T>, end: Bound<T>) -> Self {
+		let start = match start {
+			Bound::Included(start) => start,
+			_ => panic!("Range requires an Included start bound"),
+		};
+		let end = match end {
+			Bound::Excluded(end) => end,
+			_ => panic!("Range requires an Excluded end bound"),
+		};
+
+		start..end
+	}
+}
+
+impl<T> RangeBoundsExt<T> for RangeInclusive<T> {
+	fn from_bounds(start: Bound<T>, end: Bound<T>) -> Self {
+		let start = match start {
+			Bound::Included(start) => start,
+			_ => panic!("RangeInclusive requires an Included start bound"),
+		};
+		let end = match end {
+			Bound::Included(end) => end,
+			_ => panic!("RangeInclusive requires an Included end bound"),
+		};
+
+		start..=end
+	}
+}
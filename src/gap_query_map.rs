@@ -0,0 +1,403 @@
+/*
+Copyright 2022 James Forster
+
+This file is part of range_bounds_map.
+
+range_bounds_map is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+range_bounds_map is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! [`GapQueryMap`], a multi-occupant generalisation of the `Reservation`
+//! example from the crate docs.
+
+use std::collections::BTreeSet;
+use std::ops::{Bound, RangeBounds};
+
+use crate::bounds::{
+	ends_before_starts, flip_bound, EndBound, RangeBoundsExt, StartBound,
+};
+use crate::range_bounds_map::RangeBoundsMap;
+
+/// A map from non-overlapping [`RangeBounds`] to the set of `Id`s
+/// occupying them, answering "what's the nearest free gap around here,
+/// ignoring these particular occupants?".
+///
+/// Unlike [`RangeBoundsMap`], inserting the same range twice (or two
+/// overlapping ranges) under different `Id`s doesn't conflict: the
+/// overlapping portion simply ends up tagged with both `Id`s. Querying
+/// a point or range can then be asked to look straight through any
+/// `Id`s in a given ignore set, as if they weren't occupying anything,
+/// which is the shape of question a scheduler asks when it's trying to
+/// find a slot for (or extend) one of its own reservations.
+#[derive(Debug, Clone)]
+pub struct GapQueryMap<Id, I, K> {
+	occupied: RangeBoundsMap<I, K, BTreeSet<Id>>,
+}
+
+impl<Id, I, K> GapQueryMap<Id, I, K> {
+	/// Makes a new, empty `GapQueryMap`.
+	pub fn new() -> Self {
+		GapQueryMap {
+			occupied: RangeBoundsMap::new(),
+		}
+	}
+
+	/// Returns `true` if no range is occupied by any `Id`.
+	pub fn is_empty(&self) -> bool {
+		self.occupied.is_empty()
+	}
+}
+
+impl<Id, I, K> Default for GapQueryMap<Id, I, K> {
+	fn default() -> Self {
+		GapQueryMap::new()
+	}
+}
+
+impl<Id, I, K> GapQueryMap<Id, I, K>
+where
+	Id: Ord + Clone,
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	/// Marks `range` as occupied by `identifier`, on top of whatever
+	/// else already occupies it.
+	///
+	/// Any existing occupied ranges `range` overlaps are split at its
+	/// boundaries so that `identifier` is only added to the
+	/// overlapping portion, and any part of `range` not already
+	/// occupied becomes a new entry occupied solely by `identifier`.
+	///
+	/// ```
+	/// use range_bounds_map::GapQueryMap;
+	///
+	/// let mut reservations = GapQueryMap::new();
+	/// reservations.insert("Wall", -5..0);
+	/// reservations.insert("Ferris", 10..20);
+	/// reservations.insert("Corro", 15..25);
+	///
+	/// assert_eq!(reservations.gap_at_point(&12, &[]), None);
+	/// assert_eq!(
+	/// 	reservations.gap_at_point(&12, &["Ferris"]),
+	/// 	Some(0..15)
+	/// );
+	/// ```
+	pub fn insert(&mut self, identifier: Id, range: K) {
+		let overlaps: Vec<(K, BTreeSet<Id>)> = self
+			.occupied
+			.overlapping(&range)
+			.map(|(k, ids)| (k.clone(), ids.clone()))
+			.collect();
+
+		for (key, _) in &overlaps {
+			self.occupied.remove(key);
+		}
+
+		let range_start = range.start_bound().cloned();
+		let range_end = range.end_bound().cloned();
+		let mut cursor = range_start.clone();
+
+		for (index, (key, ids)) in overlaps.iter().enumerate() {
+			let key_start = key.start_bound().cloned();
+			let key_end = key.end_bound().cloned();
+
+			// The leading remainder of `key` before `range`, unchanged.
+			if index == 0
+				&& StartBound(key_start.clone()) < StartBound(range_start.clone())
+			{
+				self.occupied.insert_unchecked(
+					K::from_bounds(key_start.clone(), flip_bound(range_start.clone())),
+					ids.clone(),
+				);
+			}
+
+			// The free gap between the cursor and `key`, newly occupied
+			// by `identifier` alone.
+			let gap_end = flip_bound(key_start.clone());
+			if !ends_before_starts(&gap_end, &cursor) {
+				self.occupied.insert_unchecked(
+					K::from_bounds(cursor.clone(), gap_end),
+					BTreeSet::from([identifier.clone()]),
+				);
+			}
+
+			// The overlap between `key` and `range`, now occupied by
+			// both `identifier` and whoever already held it.
+			let mid_start = if StartBound(key_start.clone())
+				< StartBound(range_start.clone())
+			{
+				range_start.clone()
+			} else {
+				key_start.clone()
+			};
+			let mid_end =
+				if EndBound(key_end.clone()) > EndBound(range_end.clone()) {
+					range_end.clone()
+				} else {
+					key_end.clone()
+				};
+			let mut merged_ids = ids.clone();
+			merged_ids.insert(identifier.clone());
+			self.occupied
+				.insert_unchecked(K::from_bounds(mid_start, mid_end), merged_ids);
+
+			cursor = flip_bound(key_end.clone());
+
+			// The trailing remainder of `key` after `range`, unchanged.
+			if index == overlaps.len() - 1
+				&& EndBound(key_end.clone()) > EndBound(range_end.clone())
+			{
+				self.occupied.insert_unchecked(
+					K::from_bounds(flip_bound(range_end.clone()), key_end),
+					ids.clone(),
+				);
+				cursor = flip_bound(range_end.clone());
+			}
+		}
+
+		// The trailing part of `range` not covered by any overlap.
+		if !ends_before_starts(&range_end, &cursor) {
+			self.occupied.insert_unchecked(
+				K::from_bounds(cursor, range_end),
+				BTreeSet::from([identifier]),
+			);
+		}
+	}
+
+	/// Removes `identifier` from `range`, splitting any occupied entry
+	/// it partially overlaps so only the `identifier`-tagged portion is
+	/// affected. Entries left with no remaining `Id`s are removed
+	/// entirely, re-opening that stretch as a gap.
+	pub fn remove(&mut self, identifier: &Id, range: K) {
+		let overlaps: Vec<(K, BTreeSet<Id>)> = self
+			.occupied
+			.overlapping(&range)
+			.map(|(k, ids)| (k.clone(), ids.clone()))
+			.collect();
+
+		for (key, _) in &overlaps {
+			self.occupied.remove(key);
+		}
+
+		let range_start = range.start_bound().cloned();
+		let range_end = range.end_bound().cloned();
+
+		for (key, ids) in overlaps {
+			let key_start = key.start_bound().cloned();
+			let key_end = key.end_bound().cloned();
+
+			if StartBound(key_start.clone()) < StartBound(range_start.clone()) {
+				self.occupied.insert_unchecked(
+					K::from_bounds(key_start.clone(), flip_bound(range_start.clone())),
+					ids.clone(),
+				);
+			}
+
+			let mid_start = if StartBound(key_start.clone())
+				< StartBound(range_start.clone())
+			{
+				range_start.clone()
+			} else {
+				key_start.clone()
+			};
+			let mid_end =
+				if EndBound(key_end.clone()) > EndBound(range_end.clone()) {
+					range_end.clone()
+				} else {
+					key_end.clone()
+				};
+
+			let mut remaining_ids = ids.clone();
+			remaining_ids.remove(identifier);
+			if !remaining_ids.is_empty() {
+				self.occupied.insert_unchecked(
+					K::from_bounds(mid_start, mid_end),
+					remaining_ids,
+				);
+			}
+
+			if EndBound(key_end.clone()) > EndBound(range_end.clone()) {
+				self.occupied.insert_unchecked(
+					K::from_bounds(flip_bound(range_end.clone()), key_end),
+					ids,
+				);
+			}
+		}
+	}
+
+	/// Returns the maximal gap around `point` that isn't occupied by
+	/// any `Id` outside of `ignore_ids`, or `None` if `point` itself is
+	/// occupied by a non-ignored `Id`.
+	///
+	/// # Panics
+	///
+	/// The gap is built with
+	/// [`K::from_bounds()`](RangeBoundsExt::from_bounds), so it panics
+	/// under the same conditions that does: if the gap extends all the
+	/// way to one side without hitting a non-ignored occupant, it would
+	/// need an `Unbounded` bound on that side, which `K` can't represent
+	/// for the built-in [`Range`](std::ops::Range) and
+	/// [`RangeInclusive`](std::ops::RangeInclusive) impls. Pinning both
+	/// ends with a sentinel occupant (as `"Wall"` does for the low end in
+	/// [`insert()`](Self::insert)'s example) keeps every gap finite and
+	/// avoids this.
+	pub fn gap_at_point(&self, point: &I, ignore_ids: &[Id]) -> Option<K> {
+		let point_bound = (
+			Bound::Included(point.clone()),
+			Bound::Included(point.clone()),
+		);
+
+		if let Some((_, ids)) = self.occupied.overlapping(&point_bound).next() {
+			if ids.iter().any(|id| !ignore_ids.contains(id)) {
+				return None;
+			}
+		}
+
+		let start = self.blocking_boundary_before(point, ignore_ids);
+		let end = self.blocking_boundary_after(point, ignore_ids);
+
+		Some(K::from_bounds(start, end))
+	}
+
+	/// Returns every maximal gap inside `range` that isn't occupied by
+	/// any `Id` outside of `ignore_ids`, in ascending order.
+	///
+	/// # Panics
+	///
+	/// See [`gap_at_point()`](Self::gap_at_point)'s Panics section: the
+	/// same restriction applies here if `range` itself has an
+	/// `Unbounded` bound, since the first or last gap then starts or
+	/// ends `Unbounded`. Gaps are clipped to `range`, so a fully bounded
+	/// `range` never produces an `Unbounded` gap bound.
+	pub fn gaps_in_range<Q>(&self, range: Q, ignore_ids: &[Id]) -> Vec<K>
+	where
+		Q: RangeBounds<I>,
+	{
+		let mut gaps = Vec::new();
+		let mut cursor = range.start_bound().cloned();
+		let range_end = range.end_bound().cloned();
+
+		for (key, ids) in self.occupied.overlapping(&range) {
+			if ids.iter().all(|id| ignore_ids.contains(id)) {
+				continue;
+			}
+
+			let gap_start = cursor.clone();
+			let gap_end = flip_bound(key.start_bound().cloned());
+			cursor = flip_bound(key.end_bound().cloned());
+
+			if !ends_before_starts(&gap_end, &gap_start) {
+				gaps.push(K::from_bounds(gap_start, gap_end));
+			}
+		}
+
+		if !ends_before_starts(&range_end, &cursor) {
+			gaps.push(K::from_bounds(cursor, range_end));
+		}
+
+		gaps
+	}
+
+	/// Walks backwards from `point` through entries occupied solely by
+	/// ignored `Id`s, returning the start bound of the gap once it hits
+	/// a non-ignored occupant (or the start of the map).
+	fn blocking_boundary_before(&self, point: &I, ignore_ids: &[Id]) -> Bound<I> {
+		let mut cursor = Bound::Included(point.clone());
+
+		loop {
+			let before = (Bound::Unbounded, flip_bound(cursor.clone()));
+			match self.occupied.overlapping(&before).next_back() {
+				Some((key, ids)) if ids.iter().all(|id| ignore_ids.contains(id)) => {
+					cursor = key.start_bound().cloned();
+				}
+				Some((key, _)) => {
+					return flip_bound(key.end_bound().cloned());
+				}
+				None => return Bound::Unbounded,
+			}
+		}
+	}
+
+	/// Walks forwards from `point` through entries occupied solely by
+	/// ignored `Id`s, returning the end bound of the gap once it hits a
+	/// non-ignored occupant (or the end of the map).
+	fn blocking_boundary_after(&self, point: &I, ignore_ids: &[Id]) -> Bound<I> {
+		let mut cursor = Bound::Included(point.clone());
+
+		loop {
+			let after = (flip_bound(cursor.clone()), Bound::Unbounded);
+			match self.occupied.overlapping(&after).next() {
+				Some((key, ids)) if ids.iter().all(|id| ignore_ids.contains(id)) => {
+					cursor = key.end_bound().cloned();
+				}
+				Some((key, _)) => {
+					return flip_bound(key.start_bound().cloned());
+				}
+				None => return Bound::Unbounded,
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn insert_tags_overlapping_occupants_with_both_ids() {
+		let mut reservations = GapQueryMap::new();
+		reservations.insert("Wall", -5..0);
+		reservations.insert("Wall", 30..35);
+		reservations.insert("Ferris", 10..20);
+		reservations.insert("Corro", 15..25);
+
+		assert_eq!(reservations.gap_at_point(&12, &[]), None);
+		assert_eq!(reservations.gap_at_point(&22, &[]), None);
+		assert_eq!(reservations.gap_at_point(&17, &["Ferris"]), None);
+		assert_eq!(reservations.gap_at_point(&17, &["Corro"]), None);
+		assert_eq!(
+			reservations.gap_at_point(&17, &["Ferris", "Corro"]),
+			Some(0..30)
+		);
+	}
+
+	#[test]
+	fn remove_splits_the_overlap_back_out_and_reopens_an_unshared_gap() {
+		let mut reservations = GapQueryMap::new();
+		reservations.insert("Wall", -5..0);
+		reservations.insert("Wall", 30..35);
+		reservations.insert("Ferris", 10..20);
+		reservations.insert("Corro", 15..25);
+
+		reservations.remove(&"Corro", 15..25);
+
+		assert_eq!(reservations.gap_at_point(&17, &[]), None);
+		assert_eq!(reservations.gap_at_point(&22, &[]), Some(20..30));
+	}
+
+	#[test]
+	fn gaps_in_range_skips_entries_occupied_solely_by_ignored_ids() {
+		let mut reservations = GapQueryMap::new();
+		reservations.insert("Ferris", 10..20);
+		reservations.insert("Corro", 25..30);
+
+		assert_eq!(
+			reservations.gaps_in_range(0..40, &["Ferris"]),
+			vec![0..25, 30..40]
+		);
+		assert_eq!(
+			reservations.gaps_in_range(0..40, &[]),
+			vec![0..10, 20..25, 30..40]
+		);
+	}
+}
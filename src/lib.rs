@@ -28,6 +28,11 @@ along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
 //! [`RangeBoundsSet`] is like [`RangeBoundsMap`] except it
 //! uses `()` as values, as [`BTreeSet`] does for [`BTreeMap`]
 //!
+//! [`GapQueryMap`] builds on top of [`RangeBoundsMap`] to answer a
+//! different question: given several overlapping occupants tagged by
+//! an identifier, what's the nearest free gap around a point or range,
+//! ignoring some of those occupants?
+//!
 //! # Example using [`Range`]s
 //!
 //! ```
@@ -49,7 +54,7 @@ along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
 //!
 //! use range_bounds_map::RangeBoundsMap;
 //!
-//! #[derive(Debug)]
+//! #[derive(Debug, Clone)]
 //! enum Reservation {
 //! 	// Start, End (Inclusive-Inclusive)
 //! 	Finite(u8, u8),
@@ -78,11 +83,12 @@ along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
 //! }
 //!
 //! // Next we can create a custom typed RangeBoundsMap
-//! let reservation_map = RangeBoundsMap::try_from([
-//! 	(Reservation::Finite(10, 20), "Ferris".to_string()),
-//! 	(Reservation::Infinite(20), "Corro".to_string()),
-//! ])
-//! .unwrap();
+//! let reservation_map: RangeBoundsMap<u8, Reservation, String> =
+//! 	RangeBoundsMap::try_from([
+//! 		(Reservation::Finite(10, 20), "Ferris".to_string()),
+//! 		(Reservation::Infinite(20), "Corro".to_string()),
+//! 	])
+//! 	.unwrap();
 //!
 //! for (reservation, name) in reservation_map.overlapping(&(16..17))
 //! {
@@ -119,19 +125,25 @@ along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
 //!
 //! To summarise:
 //!
-//! - No coalescing/merge insert functions, yet
-//! - No `gaps()` iterator function, yet
 //! - Missing some functions common to BTreeMap and BTreeSet like:
 //!   - `clear()`
-//!   - `is_subset()`
 //!   - etc... a bunch more
 //! - Sub-optimal use of unnecessary `cloned()` just to placate the borrow checker
 //! - The data structures are lacking a lot of useful traits, such as:
-//!   - Serde: Serialize and Deserialize
 //!   - FromIterator
 //!   - IntoIterator
 //!   - Probably a bunch more
 //!
+//! # Feature Flags
+//!
+//! - `serde`: implements [`Serialize`](serde::Serialize) and
+//!   [`Deserialize`](serde::Deserialize) for [`RangeBoundsMap`] and
+//!   [`RangeBoundsSet`], as a sequence of `(range, value)` pairs (just
+//!   `range` for the set). Deserializing goes through the same
+//!   validating insert path as [`insert()`](RangeBoundsMap::insert),
+//!   so overlapping or malformed input is rejected with a serde error
+//!   instead of silently producing a corrupt map.
+//!
 //! # Credit
 //!
 //! I originally came up with the `StartBound`: [`Ord`] bodge on my
@@ -193,8 +205,13 @@ along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
 #![allow(clippy::tabs_in_doc_comments)]
 #![allow(clippy::needless_return)]
 pub(crate) mod bounds;
+pub mod gap_query_map;
 pub mod range_bounds_map;
 pub mod range_bounds_set;
+#[cfg(feature = "serde")]
+mod serde_impl;
 
-pub use crate::range_bounds_map::{InsertError, RangeBoundsMap};
-pub use crate::range_bounds_set::RangeBoundsSet;
+pub use crate::bounds::{RangeBoundsExt, StepLite};
+pub use crate::gap_query_map::GapQueryMap;
+pub use crate::range_bounds_map::{Gaps, InsertError, RangeBoundsMap};
+pub use crate::range_bounds_set::{RangeBoundsSet, SetOp};
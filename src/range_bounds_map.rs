@@ -0,0 +1,1032 @@
+/*
+Copyright 2022 James Forster
+
+This file is part of range_bounds_map.
+
+range_bounds_map is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+range_bounds_map is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+use crate::bounds::{
+	bounds_touch, bounds_touch_backwards, ends_before_starts, flip_bound,
+	starts_after_ends, EndBound, RangeBoundsExt, StartBound, StepLite,
+};
+
+/// An ordered map of non-overlapping [`RangeBounds`] to `V`.
+///
+/// Similar to [`BTreeMap`] except [`RangeBoundsMap`] uses any type that
+/// implements [`RangeBounds<I>`] as keys, while maintaining two
+/// invariants:
+/// - No two keys may overlap.
+/// - A keys' `start_bound()` <= its `end_bound()`.
+///
+/// `I` is the domain the ranges are defined over (e.g. `i32`), and `K`
+/// is the concrete [`RangeBounds<I>`] type used as the key (e.g.
+/// `Range<i32>`, or a custom type).
+#[derive(Debug, Clone)]
+pub struct RangeBoundsMap<I, K, V> {
+	pub(crate) inner: BTreeMap<StartBound<I>, (K, V)>,
+}
+
+/// An error returned when an [`insert()`](RangeBoundsMap::insert) would
+/// violate one of [`RangeBoundsMap`]'s two invariants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+	/// The range given to an insert method overlapped one or more
+	/// existing entries.
+	OverlapsExistingEntry,
+	/// The range given to an insert method starts strictly after it
+	/// ends.
+	StartAfterEnd,
+}
+
+impl<I, K, V> RangeBoundsMap<I, K, V> {
+	/// Makes a new, empty `RangeBoundsMap`.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let map: RangeBoundsMap<i32, std::ops::Range<i32>, bool> =
+	/// 	RangeBoundsMap::new();
+	/// ```
+	pub fn new() -> Self {
+		RangeBoundsMap {
+			inner: BTreeMap::new(),
+		}
+	}
+
+	/// Returns the number of entries in the map.
+	pub fn len(&self) -> usize {
+		self.inner.len()
+	}
+
+	/// Returns `true` if the map contains no entries.
+	pub fn is_empty(&self) -> bool {
+		self.inner.is_empty()
+	}
+}
+
+impl<I, K, V> Default for RangeBoundsMap<I, K, V> {
+	fn default() -> Self {
+		RangeBoundsMap::new()
+	}
+}
+
+impl<I, K, V> RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	/// Returns an iterator over every `(&K, &V)` whose key overlaps
+	/// `range`, in ascending order.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..5, true).unwrap();
+	/// map.insert(5..10, false).unwrap();
+	///
+	/// let overlapping: Vec<_> = map.overlapping(&(-2..5)).collect();
+	/// assert_eq!(overlapping, [(&(0..5), &true)]);
+	/// ```
+	pub fn overlapping<Q>(
+		&self,
+		range: &Q,
+	) -> impl DoubleEndedIterator<Item = (&K, &V)>
+	where
+		Q: RangeBounds<I>,
+	{
+		let query_start = range.start_bound().cloned();
+		let query_end_as_start_bound = match range.end_bound() {
+			Bound::Included(point) => {
+				Bound::Included(StartBound(Bound::Included(point.clone())))
+			}
+			Bound::Excluded(point) => {
+				Bound::Excluded(StartBound(Bound::Included(point.clone())))
+			}
+			Bound::Unbounded => Bound::Unbounded,
+		};
+
+		self.inner
+			.range((Bound::Unbounded, query_end_as_start_bound))
+			.map(|(_, (k, v))| (k, v))
+			.filter(move |(k, _)| {
+				!ends_before_starts(&k.end_bound().cloned(), &query_start)
+			})
+	}
+
+	/// Returns `true` if any entry in the map overlaps `range`.
+	pub fn overlaps<Q>(&self, range: &Q) -> bool
+	where
+		Q: RangeBounds<I>,
+	{
+		self.overlapping(range).next().is_some()
+	}
+
+	/// Returns `true` if an entry in the map covers `point`.
+	pub fn contains_point(&self, point: &I) -> bool {
+		self.overlapping(&(
+			Bound::Included(point.clone()),
+			Bound::Included(point.clone()),
+		))
+		.next()
+		.is_some()
+	}
+
+	/// Returns the value of the entry covering `point`, if any.
+	pub fn get_at_point(&self, point: &I) -> Option<&V> {
+		self.overlapping(&(
+			Bound::Included(point.clone()),
+			Bound::Included(point.clone()),
+		))
+		.next()
+		.map(|(_, v)| v)
+	}
+
+	/// Returns an iterator over every `(&K, &V)` in the map, in
+	/// ascending order.
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> {
+		self.inner.values().map(|(k, v)| (k, v))
+	}
+
+	/// Removes the entry keyed by `range`'s `start_bound()`, returning
+	/// its value if there was one.
+	///
+	/// Unlike [`insert()`](RangeBoundsMap::insert)'s overlap check, this
+	/// looks the entry up by exact start bound, so it only removes an
+	/// entry if `range` (or at least its start) matches one already in
+	/// the map.
+	pub(crate) fn remove(&mut self, range: &K) -> Option<V> {
+		self.inner
+			.remove(&StartBound(range.start_bound().cloned()))
+			.map(|(_, v)| v)
+	}
+
+	/// Inserts `(range, value)` into the map without checking the two
+	/// invariants, returning the entry it replaced that had exactly the
+	/// same `start_bound()`, if any. Other entries it overlaps are left
+	/// in the map and are not returned.
+	///
+	/// Prefer [`insert()`](RangeBoundsMap::insert) unless you have
+	/// already checked `range` doesn't overlap anything, as it is much
+	/// harder to accidentally corrupt the map with.
+	pub(crate) fn insert_unchecked(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Option<(K, V)> {
+		self.inner
+			.insert(StartBound(range.start_bound().cloned()), (range, value))
+	}
+
+	/// Inserts `(range, value)` into the map, as long as `range` does
+	/// not overlap any range already in the map and doesn't start after
+	/// it ends.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, or
+	/// [`InsertError::OverlapsExistingEntry`] if `range` overlaps an
+	/// existing entry, leaving the map unchanged either way.
+	///
+	/// ```
+	/// use range_bounds_map::{InsertError, RangeBoundsMap};
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..5, true).unwrap();
+	///
+	/// assert_eq!(
+	/// 	map.insert(4..10, false),
+	/// 	Err(InsertError::OverlapsExistingEntry)
+	/// );
+	/// ```
+	pub fn insert(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Result<(), InsertError> {
+		if starts_after_ends(
+			&range.start_bound().cloned(),
+			&range.end_bound().cloned(),
+		) {
+			return Err(InsertError::StartAfterEnd);
+		}
+
+		if self.overlaps(&range) {
+			return Err(InsertError::OverlapsExistingEntry);
+		}
+
+		self.insert_unchecked(range, value);
+
+		Ok(())
+	}
+}
+
+impl<I, K, V> RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	/// Returns an iterator over the maximal sub-ranges of `outer` that
+	/// aren't covered by any entry in the map, in ascending order.
+	///
+	/// The iterator is lazy: it only looks as far into the map as it's
+	/// asked to, and implements [`DoubleEndedIterator`] so it can be
+	/// driven from either end (or both, with e.g. `.rev()`).
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(2..4, true).unwrap();
+	/// map.insert(6..8, false).unwrap();
+	///
+	/// let gaps: Vec<_> = map.gaps(0..10).collect();
+	/// assert_eq!(gaps, [0..2, 4..6, 8..10]);
+	/// ```
+	///
+	/// # Panics
+	///
+	/// A yielded gap is built with [`K::from_bounds()`](RangeBoundsExt::from_bounds),
+	/// so it panics under the same conditions that does: if the gap at
+	/// either end of `outer` would need an `Unbounded` bound but `K`
+	/// can't represent one, which is the case for the built-in [`Range`]
+	/// and [`RangeInclusive`] impls. This happens if `outer` itself has
+	/// an `Unbounded` bound (e.g. `map.gaps(..)`) and the map has no
+	/// entry reaching all the way to that side, including on an empty
+	/// map. Pass a fully bounded `outer` (or a custom `K` that can
+	/// represent `Unbounded`, like the `Reservation` example in the
+	/// crate docs) to avoid this.
+	///
+	/// [`Range`]: std::ops::Range
+	/// [`RangeInclusive`]: std::ops::RangeInclusive
+	pub fn gaps<'a, Q>(&'a self, outer: Q) -> Gaps<'a, I, K, V>
+	where
+		Q: RangeBounds<I> + 'a,
+	{
+		let front_cursor = outer.start_bound().cloned();
+		let back_cursor = outer.end_bound().cloned();
+
+		Gaps {
+			overlapping: Box::new(self.overlapping(&outer)),
+			front_cursor,
+			back_cursor,
+			done: false,
+		}
+	}
+}
+
+/// An iterator over the gaps of a [`RangeBoundsMap`], created by
+/// [`RangeBoundsMap::gaps()`].
+pub struct Gaps<'a, I, K, V> {
+	overlapping: Box<dyn DoubleEndedIterator<Item = (&'a K, &'a V)> + 'a>,
+	front_cursor: Bound<I>,
+	back_cursor: Bound<I>,
+	done: bool,
+}
+
+impl<'a, I, K, V> Iterator for Gaps<'a, I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I>,
+{
+	type Item = K;
+
+	fn next(&mut self) -> Option<K> {
+		loop {
+			if self.done {
+				return None;
+			}
+
+			match self.overlapping.next() {
+				Some((key, _)) => {
+					let gap_start = self.front_cursor.clone();
+					let gap_end = flip_bound(key.start_bound().cloned());
+					self.front_cursor = flip_bound(key.end_bound().cloned());
+
+					if ends_before_starts(&gap_end, &gap_start) {
+						continue;
+					}
+
+					return Some(K::from_bounds(gap_start, gap_end));
+				}
+				None => {
+					self.done = true;
+
+					let gap_start = self.front_cursor.clone();
+					let gap_end = self.back_cursor.clone();
+
+					return (!ends_before_starts(&gap_end, &gap_start))
+						.then(|| K::from_bounds(gap_start, gap_end));
+				}
+			}
+		}
+	}
+}
+
+impl<'a, I, K, V> DoubleEndedIterator for Gaps<'a, I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I>,
+{
+	fn next_back(&mut self) -> Option<K> {
+		loop {
+			if self.done {
+				return None;
+			}
+
+			match self.overlapping.next_back() {
+				Some((key, _)) => {
+					let gap_end = self.back_cursor.clone();
+					let gap_start = flip_bound(key.end_bound().cloned());
+					self.back_cursor = flip_bound(key.start_bound().cloned());
+
+					if ends_before_starts(&gap_end, &gap_start) {
+						continue;
+					}
+
+					return Some(K::from_bounds(gap_start, gap_end));
+				}
+				None => {
+					self.done = true;
+
+					let gap_start = self.front_cursor.clone();
+					let gap_end = self.back_cursor.clone();
+
+					return (!ends_before_starts(&gap_end, &gap_start))
+						.then(|| K::from_bounds(gap_start, gap_end));
+				}
+			}
+		}
+	}
+}
+
+impl<I, K, V, const N: usize> TryFrom<[(K, V); N]> for RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	type Error = InsertError;
+
+	/// Builds a `RangeBoundsMap` from an array of `(range, value)`
+	/// pairs, failing if any two of them overlap.
+	fn try_from(pairs: [(K, V); N]) -> Result<Self, Self::Error> {
+		let mut map = RangeBoundsMap::new();
+
+		for (range, value) in pairs {
+			map.insert(range, value)?;
+		}
+
+		Ok(map)
+	}
+}
+
+impl<I, K, V> RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+	V: PartialEq,
+{
+	/// Finds the entry immediately before `start`, if its end bound
+	/// touches `start`.
+	fn touching_before(
+		&self,
+		start: &Bound<I>,
+		sub_one: &impl Fn(&I) -> I,
+	) -> Option<StartBound<I>> {
+		let (before_start, (before_key, _)) = self
+			.inner
+			.range((
+				Bound::Unbounded,
+				Bound::Excluded(StartBound(start.clone())),
+			))
+			.next_back()?;
+
+		bounds_touch_backwards(&before_key.end_bound().cloned(), start, sub_one)
+			.then(|| before_start.clone())
+	}
+
+	/// Finds the entry immediately after `end`, if its start bound
+	/// touches `end`.
+	fn touching_after(
+		&self,
+		end: &Bound<I>,
+		add_one: &impl Fn(&I) -> I,
+	) -> Option<StartBound<I>> {
+		let after_start_bound = match end {
+			Bound::Included(point) | Bound::Excluded(point) => {
+				Bound::Included(StartBound(Bound::Included(point.clone())))
+			}
+			Bound::Unbounded => return None,
+		};
+
+		let (after_start, (after_key, _)) = self
+			.inner
+			.range((after_start_bound, Bound::Unbounded))
+			.next()?;
+
+		bounds_touch(end, &after_key.start_bound().cloned(), add_one)
+			.then(|| after_start.clone())
+	}
+
+	/// The shared machinery behind [`insert_merge_touching`],
+	/// [`insert_merge_overlapping`] and
+	/// [`insert_merge_touching_or_overlapping`].
+	///
+	/// [`insert_merge_touching`]: RangeBoundsMap::insert_merge_touching
+	/// [`insert_merge_overlapping`]: RangeBoundsMap::insert_merge_overlapping
+	/// [`insert_merge_touching_or_overlapping`]: RangeBoundsMap::insert_merge_touching_or_overlapping
+	fn merge_insert_with_step_fns(
+		&mut self,
+		range: K,
+		value: V,
+		add_one: &impl Fn(&I) -> I,
+		sub_one: &impl Fn(&I) -> I,
+		merge_touching: bool,
+		merge_overlapping: bool,
+	) -> Result<K, InsertError> {
+		if starts_after_ends(
+			&range.start_bound().cloned(),
+			&range.end_bound().cloned(),
+		) {
+			return Err(InsertError::StartAfterEnd);
+		}
+
+		let mut to_remove = Vec::new();
+		let mut merged_start = range.start_bound().cloned();
+		let mut merged_end = range.end_bound().cloned();
+
+		for (key, existing_value) in self.overlapping(&range) {
+			if !merge_overlapping || *existing_value != value {
+				return Err(InsertError::OverlapsExistingEntry);
+			}
+
+			if StartBound(key.start_bound().cloned())
+				< StartBound(merged_start.clone())
+			{
+				merged_start = key.start_bound().cloned();
+			}
+			if EndBound(key.end_bound().cloned())
+				> EndBound(merged_end.clone())
+			{
+				merged_end = key.end_bound().cloned();
+			}
+
+			to_remove.push(StartBound(key.start_bound().cloned()));
+		}
+
+		if merge_touching {
+			while let Some(before) =
+				self.touching_before(&merged_start, sub_one)
+			{
+				let (key, existing_value) = self.inner.get(&before).unwrap();
+				if *existing_value != value {
+					break;
+				}
+				merged_start = key.start_bound().cloned();
+				to_remove.push(before);
+			}
+			while let Some(after) = self.touching_after(&merged_end, add_one)
+			{
+				let (key, existing_value) = self.inner.get(&after).unwrap();
+				if *existing_value != value {
+					break;
+				}
+				merged_end = key.end_bound().cloned();
+				to_remove.push(after);
+			}
+		}
+
+		for start in &to_remove {
+			self.inner.remove(start);
+		}
+
+		let merged_range = K::from_bounds(merged_start, merged_end);
+		self.insert_unchecked(merged_range.clone(), value);
+
+		Ok(merged_range)
+	}
+
+	/// Inserts `(range, value)`, merging it with any existing entries
+	/// it is adjacent (but not overlapping) to, as long as they carry
+	/// an equal value.
+	///
+	/// Returns the final, possibly widened, range on success.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, or
+	/// [`InsertError::OverlapsExistingEntry`] if `range` overlaps an
+	/// existing entry, leaving the map unchanged either way. Unlike
+	/// [`insert_merge_overlapping()`](RangeBoundsMap::insert_merge_overlapping),
+	/// overlaps are never merged here, even if the value matches.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..5, true).unwrap();
+	/// map.insert(10..15, true).unwrap();
+	///
+	/// assert_eq!(map.insert_merge_touching(5..10, true), Ok(0..15));
+	/// ```
+	pub fn insert_merge_touching(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.insert_merge_touching_with_step_fns(
+			range,
+			value,
+			I::add_one,
+			I::sub_one,
+		)
+	}
+
+	/// Identical to
+	/// [`insert_merge_touching()`](RangeBoundsMap::insert_merge_touching)
+	/// except it takes `add_one`/`sub_one` functions directly, for key
+	/// domains that don't implement [`StepLite`].
+	pub fn insert_merge_touching_with_step_fns(
+		&mut self,
+		range: K,
+		value: V,
+		add_one: impl Fn(&I) -> I,
+		sub_one: impl Fn(&I) -> I,
+	) -> Result<K, InsertError> {
+		self.merge_insert_with_step_fns(
+			range, value, &add_one, &sub_one, true, false,
+		)
+	}
+
+	/// Inserts `(range, value)`, merging it with any existing entries
+	/// it overlaps, as long as they carry an equal value.
+	///
+	/// Returns the final, possibly widened, range on success. Touching
+	/// (but not overlapping) entries are left untouched even if their
+	/// value matches; use
+	/// [`insert_merge_touching_or_overlapping()`](RangeBoundsMap::insert_merge_touching_or_overlapping)
+	/// for that.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, or
+	/// [`InsertError::OverlapsExistingEntry`] if `range` overlaps an
+	/// existing entry with a different value, leaving the map unchanged
+	/// either way.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..5, true).unwrap();
+	///
+	/// assert_eq!(map.insert_merge_overlapping(3..10, true), Ok(0..10));
+	/// ```
+	pub fn insert_merge_overlapping(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.insert_merge_overlapping_with_step_fns(
+			range,
+			value,
+			I::add_one,
+			I::sub_one,
+		)
+	}
+
+	/// Identical to
+	/// [`insert_merge_overlapping()`](RangeBoundsMap::insert_merge_overlapping)
+	/// except it takes `add_one`/`sub_one` functions directly, for key
+	/// domains that don't implement [`StepLite`].
+	pub fn insert_merge_overlapping_with_step_fns(
+		&mut self,
+		range: K,
+		value: V,
+		add_one: impl Fn(&I) -> I,
+		sub_one: impl Fn(&I) -> I,
+	) -> Result<K, InsertError> {
+		self.merge_insert_with_step_fns(
+			range, value, &add_one, &sub_one, false, true,
+		)
+	}
+
+	/// Inserts `(range, value)`, merging it with any existing entries
+	/// it overlaps *or* touches, as long as they carry an equal value.
+	///
+	/// Returns the final, possibly widened, range on success.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, or
+	/// [`InsertError::OverlapsExistingEntry`] if `range` overlaps an
+	/// existing entry with a different value, leaving the map unchanged
+	/// either way.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..=4, true).unwrap();
+	/// map.insert(7..=12, true).unwrap();
+	///
+	/// assert_eq!(
+	/// 	map.insert_merge_touching_or_overlapping(5..=9, true),
+	/// 	Ok(0..=12)
+	/// );
+	/// ```
+	pub fn insert_merge_touching_or_overlapping(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.insert_merge_touching_or_overlapping_with_step_fns(
+			range,
+			value,
+			I::add_one,
+			I::sub_one,
+		)
+	}
+
+	/// Identical to
+	/// [`insert_merge_touching_or_overlapping()`](RangeBoundsMap::insert_merge_touching_or_overlapping)
+	/// except it takes `add_one`/`sub_one` functions directly, for key
+	/// domains that don't implement [`StepLite`].
+	pub fn insert_merge_touching_or_overlapping_with_step_fns(
+		&mut self,
+		range: K,
+		value: V,
+		add_one: impl Fn(&I) -> I,
+		sub_one: impl Fn(&I) -> I,
+	) -> Result<K, InsertError> {
+		self.merge_insert_with_step_fns(
+			range, value, &add_one, &sub_one, true, true,
+		)
+	}
+}
+
+impl<I, K, V> RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+	V: Clone,
+{
+	/// Builds the end bound of the fragment of an entry that survives
+	/// up to (but not including) `cut`, matching `shape`'s own kind of
+	/// end bound (`Excluded` like [`Range`](std::ops::Range), or
+	/// `Included` like [`RangeInclusive`](std::ops::RangeInclusive)).
+	fn fragment_end_before(
+		shape: &Bound<I>,
+		cut: &Bound<I>,
+		sub_one: &impl Fn(&I) -> I,
+	) -> Bound<I> {
+		match shape {
+			Bound::Included(_) => match cut {
+				Bound::Included(point) => {
+					Bound::Included(sub_one(point))
+				}
+				Bound::Excluded(point) => {
+					Bound::Included(point.clone())
+				}
+				Bound::Unbounded => Bound::Unbounded,
+			},
+			Bound::Excluded(_) | Bound::Unbounded => flip_bound(cut.clone()),
+		}
+	}
+
+	/// Builds the start bound of the fragment of an entry that
+	/// survives from just after `cut` onwards, matching `shape`'s own
+	/// kind of start bound.
+	fn fragment_start_after(
+		shape: &Bound<I>,
+		cut: &Bound<I>,
+		add_one: &impl Fn(&I) -> I,
+	) -> Bound<I> {
+		match shape {
+			Bound::Included(_) => match cut {
+				Bound::Included(point) => {
+					Bound::Included(add_one(point))
+				}
+				Bound::Excluded(point) => {
+					Bound::Included(point.clone())
+				}
+				Bound::Unbounded => Bound::Unbounded,
+			},
+			Bound::Excluded(_) | Bound::Unbounded => flip_bound(cut.clone()),
+		}
+	}
+
+	/// Inserts `(range, value)` unconditionally, trimming or removing
+	/// any existing entries that overlap `range` to make room for it
+	/// rather than erroring.
+	///
+	/// An entry entirely covered by `range` is removed outright. An
+	/// entry that straddles one of `range`'s ends is cut: the
+	/// overlapping part is discarded and the remaining, non-overlapping
+	/// part is re-inserted with its original value, unchanged. An
+	/// entry straddling *both* ends of `range` (i.e. `range` sits
+	/// entirely inside it) produces two such leftover fragments.
+	///
+	/// Returns every entry that `range` overlapped, in their original,
+	/// untrimmed form, in ascending order.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, leaving the
+	/// map unchanged.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsMap;
+	///
+	/// let mut map = RangeBoundsMap::new();
+	/// map.insert(0..10, "a").unwrap();
+	///
+	/// assert_eq!(map.insert_overwrite(4..6, "b"), Ok(vec![(0..10, "a")]));
+	///
+	/// let entries: Vec<_> = map.iter().collect();
+	/// assert_eq!(
+	/// 	entries,
+	/// 	[(&(0..4), &"a"), (&(4..6), &"b"), (&(6..10), &"a")]
+	/// );
+	/// ```
+	pub fn insert_overwrite(
+		&mut self,
+		range: K,
+		value: V,
+	) -> Result<Vec<(K, V)>, InsertError>
+	where
+		I: StepLite,
+	{
+		self.insert_overwrite_with_step_fns(
+			range,
+			value,
+			I::add_one,
+			I::sub_one,
+		)
+	}
+
+	/// Identical to
+	/// [`insert_overwrite()`](RangeBoundsMap::insert_overwrite) except
+	/// it takes `add_one`/`sub_one` functions directly, for key domains
+	/// that don't implement [`StepLite`].
+	pub fn insert_overwrite_with_step_fns(
+		&mut self,
+		range: K,
+		value: V,
+		add_one: impl Fn(&I) -> I,
+		sub_one: impl Fn(&I) -> I,
+	) -> Result<Vec<(K, V)>, InsertError> {
+		if starts_after_ends(
+			&range.start_bound().cloned(),
+			&range.end_bound().cloned(),
+		) {
+			return Err(InsertError::StartAfterEnd);
+		}
+
+		let displaced: Vec<(K, V)> = self
+			.overlapping(&range)
+			.map(|(key, value)| (key.clone(), value.clone()))
+			.collect();
+
+		let range_start = range.start_bound().cloned();
+		let range_end = range.end_bound().cloned();
+
+		for (key, existing_value) in &displaced {
+			self.inner
+				.remove(&StartBound(key.start_bound().cloned()));
+
+			if StartBound(key.start_bound().cloned())
+				< StartBound(range_start.clone())
+			{
+				let fragment_end = Self::fragment_end_before(
+					&key.end_bound().cloned(),
+					&range_start,
+					&sub_one,
+				);
+				self.insert_unchecked(
+					K::from_bounds(
+						key.start_bound().cloned(),
+						fragment_end,
+					),
+					existing_value.clone(),
+				);
+			}
+
+			if EndBound(key.end_bound().cloned())
+				> EndBound(range_end.clone())
+			{
+				let fragment_start = Self::fragment_start_after(
+					&key.start_bound().cloned(),
+					&range_end,
+					&add_one,
+				);
+				self.insert_unchecked(
+					K::from_bounds(fragment_start, key.end_bound().cloned()),
+					existing_value.clone(),
+				);
+			}
+		}
+
+		self.insert_unchecked(range, value);
+
+		Ok(displaced)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ops::Range;
+
+	use super::*;
+
+	#[test]
+	fn insert_merge_touching_absorbs_a_chain_of_neighbours() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..5, true).unwrap();
+		map.insert(5..10, true).unwrap();
+
+		assert_eq!(map.insert_merge_touching(10..15, true), Ok(0..15));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..15), &true)]
+		);
+	}
+
+	#[test]
+	fn insert_merge_touching_or_overlapping_absorbs_a_chain_of_neighbours() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..=4, true).unwrap();
+		map.insert(5..=9, true).unwrap();
+
+		assert_eq!(
+			map.insert_merge_touching_or_overlapping(10..=14, true),
+			Ok(0..=14)
+		);
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..=14), &true)]
+		);
+	}
+
+	#[test]
+	fn insert_merge_touching_does_not_absorb_a_different_valued_neighbour() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..5, true).unwrap();
+		map.insert(5..10, false).unwrap();
+
+		assert_eq!(map.insert_merge_touching(10..15, true), Ok(10..15));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..5), &true), (&(5..10), &false), (&(10..15), &true)]
+		);
+	}
+
+	#[test]
+	fn gaps_handles_a_bounded_query_on_an_empty_map() {
+		let map: RangeBoundsMap<i32, Range<i32>, bool> = RangeBoundsMap::new();
+
+		assert_eq!(map.gaps(0..10).collect::<Vec<_>>(), vec![0..10]);
+	}
+
+	#[test]
+	fn gaps_handles_gaps_at_either_end_of_a_bounded_query() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(2..4, true).unwrap();
+
+		assert_eq!(map.gaps(0..10).collect::<Vec<_>>(), [0..2, 4..10]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn gaps_panics_on_an_unbounded_query_against_an_unbounded_incapable_key() {
+		let map: RangeBoundsMap<i32, Range<i32>, bool> = RangeBoundsMap::new();
+
+		let _ = map.gaps(..).collect::<Vec<_>>();
+	}
+
+	#[test]
+	fn insert_rejects_a_backwards_range() {
+		let mut map: RangeBoundsMap<i32, (Bound<i32>, Bound<i32>), bool> =
+			RangeBoundsMap::new();
+
+		assert_eq!(
+			map.insert((Bound::Included(10), Bound::Included(5)), true),
+			Err(InsertError::StartAfterEnd)
+		);
+		assert!(map.is_empty());
+	}
+
+	#[test]
+	#[allow(clippy::reversed_empty_ranges)]
+	fn insert_merge_touching_rejects_a_backwards_range() {
+		let mut map: RangeBoundsMap<i32, Range<i32>, bool> =
+			RangeBoundsMap::new();
+
+		assert_eq!(
+			map.insert_merge_touching(10..5, true),
+			Err(InsertError::StartAfterEnd)
+		);
+		assert!(map.is_empty());
+	}
+
+	#[test]
+	fn insert_overwrite_removes_an_entry_it_fully_covers() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..10, "a").unwrap();
+
+		assert_eq!(map.insert_overwrite(0..10, "b"), Ok(vec![(0..10, "a")]));
+		assert_eq!(map.iter().collect::<Vec<_>>(), [(&(0..10), &"b")]);
+	}
+
+	#[test]
+	fn insert_overwrite_trims_an_entry_it_straddles_the_leading_edge_of() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..10, "a").unwrap();
+
+		assert_eq!(map.insert_overwrite(5..15, "b"), Ok(vec![(0..10, "a")]));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..5), &"a"), (&(5..15), &"b")]
+		);
+	}
+
+	#[test]
+	fn insert_overwrite_trims_an_entry_it_straddles_the_trailing_edge_of() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(5..15, "a").unwrap();
+
+		assert_eq!(map.insert_overwrite(0..10, "b"), Ok(vec![(5..15, "a")]));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..10), &"b"), (&(10..15), &"a")]
+		);
+	}
+
+	#[test]
+	fn insert_overwrite_splits_an_entry_it_sits_entirely_inside_of() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..10, "a").unwrap();
+
+		assert_eq!(map.insert_overwrite(4..6, "b"), Ok(vec![(0..10, "a")]));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..4), &"a"), (&(4..6), &"b"), (&(6..10), &"a")]
+		);
+	}
+
+	#[test]
+	fn insert_overwrite_splits_a_range_inclusive_entry_using_sub_one_and_add_one(
+	) {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..=9, "a").unwrap();
+
+		assert_eq!(map.insert_overwrite(4..=5, "b"), Ok(vec![(0..=9, "a")]));
+		assert_eq!(
+			map.iter().collect::<Vec<_>>(),
+			[(&(0..=3), &"a"), (&(4..=5), &"b"), (&(6..=9), &"a")]
+		);
+	}
+
+	#[test]
+	#[allow(clippy::reversed_empty_ranges)]
+	fn insert_overwrite_rejects_a_backwards_range() {
+		let mut map: RangeBoundsMap<i32, Range<i32>, &str> =
+			RangeBoundsMap::new();
+		map.insert(0..10, "a").unwrap();
+
+		assert_eq!(
+			map.insert_overwrite(10..5, "b"),
+			Err(InsertError::StartAfterEnd)
+		);
+		assert_eq!(map.iter().collect::<Vec<_>>(), [(&(0..10), &"a")]);
+	}
+}
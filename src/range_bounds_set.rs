@@ -0,0 +1,694 @@
+/*
+Copyright 2022 James Forster
+
+This file is part of range_bounds_map.
+
+range_bounds_map is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+range_bounds_map is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::iter::Peekable;
+use std::ops::{BitAnd, BitOr, BitXor, Bound, RangeBounds, Sub};
+
+use crate::bounds::{
+	ends_before_starts, flip_bound, EndBound, RangeBoundsExt, StartBound,
+	StepLite,
+};
+use crate::range_bounds_map::{Gaps, InsertError, RangeBoundsMap};
+
+/// An ordered set of non-overlapping [`RangeBounds`], backed by a
+/// [`RangeBoundsMap`] with `()` values, the same way [`BTreeSet`] is
+/// backed by a [`BTreeMap`].
+///
+/// [`BTreeSet`]: std::collections::BTreeSet
+/// [`BTreeMap`]: std::collections::BTreeMap
+#[derive(Debug, Clone)]
+pub struct RangeBoundsSet<I, K> {
+	inner: RangeBoundsMap<I, K, ()>,
+}
+
+impl<I, K> RangeBoundsSet<I, K> {
+	/// Makes a new, empty `RangeBoundsSet`.
+	pub fn new() -> Self {
+		RangeBoundsSet {
+			inner: RangeBoundsMap::new(),
+		}
+	}
+
+	/// Returns the number of ranges in the set.
+	pub fn len(&self) -> usize {
+		self.inner.len()
+	}
+
+	/// Returns `true` if the set contains no ranges.
+	pub fn is_empty(&self) -> bool {
+		self.inner.is_empty()
+	}
+}
+
+impl<I, K> Default for RangeBoundsSet<I, K> {
+	fn default() -> Self {
+		RangeBoundsSet::new()
+	}
+}
+
+impl<I, K> RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	/// Returns an iterator over every range in the set overlapping
+	/// `range`, in ascending order.
+	pub fn overlapping<Q>(&self, range: &Q) -> impl DoubleEndedIterator<Item = &K>
+	where
+		Q: RangeBounds<I>,
+	{
+		self.inner.overlapping(range).map(|(k, _)| k)
+	}
+
+	/// Returns `true` if any range in the set overlaps `range`.
+	pub fn overlaps<Q>(&self, range: &Q) -> bool
+	where
+		Q: RangeBounds<I>,
+	{
+		self.inner.overlaps(range)
+	}
+
+	/// Returns `true` if a range in the set covers `point`.
+	pub fn contains_point(&self, point: &I) -> bool {
+		self.inner.contains_point(point)
+	}
+
+	/// Returns an iterator over every range in the set, in ascending
+	/// order.
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &K> {
+		self.inner.iter().map(|(k, _)| k)
+	}
+
+	/// Inserts `range` into the set, as long as it does not overlap
+	/// any range already in the set.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::OverlapsExistingEntry`] if `range`
+	/// overlaps an existing entry, leaving the set unchanged.
+	pub fn insert(&mut self, range: K) -> Result<(), InsertError> {
+		self.inner.insert(range, ())
+	}
+}
+
+impl<I, K> RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	/// Returns an iterator over the maximal sub-ranges of `outer` that
+	/// aren't covered by any range in the set, in ascending order.
+	///
+	/// See [`RangeBoundsMap::gaps()`] for the full semantics.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut set = RangeBoundsSet::new();
+	/// set.insert(2..4).unwrap();
+	/// set.insert(6..8).unwrap();
+	///
+	/// let gaps: Vec<_> = set.gaps(0..10).collect();
+	/// assert_eq!(gaps, [0..2, 4..6, 8..10]);
+	/// ```
+	pub fn gaps<'a, Q>(&'a self, outer: Q) -> Gaps<'a, I, K, ()>
+	where
+		Q: RangeBounds<I> + 'a,
+	{
+		self.inner.gaps(outer)
+	}
+}
+
+impl<I, K, const N: usize> TryFrom<[K; N]> for RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	type Error = InsertError;
+
+	/// Builds a `RangeBoundsSet` from an array of ranges, failing if
+	/// any two of them overlap.
+	fn try_from(ranges: [K; N]) -> Result<Self, Self::Error> {
+		let mut set = RangeBoundsSet::new();
+
+		for range in ranges {
+			set.insert(range)?;
+		}
+
+		Ok(set)
+	}
+}
+
+impl<I, K> RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	/// Inserts `range`, merging it with any existing ranges it is
+	/// adjacent (but not overlapping) to.
+	///
+	/// See [`RangeBoundsMap::insert_merge_touching()`] for the full
+	/// semantics.
+	pub fn insert_merge_touching(
+		&mut self,
+		range: K,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.inner.insert_merge_touching(range, ())
+	}
+
+	/// Inserts `range`, merging it with any existing ranges it
+	/// overlaps.
+	///
+	/// See [`RangeBoundsMap::insert_merge_overlapping()`] for the full
+	/// semantics.
+	pub fn insert_merge_overlapping(
+		&mut self,
+		range: K,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.inner.insert_merge_overlapping(range, ())
+	}
+
+	/// Inserts `range`, merging it with any existing ranges it
+	/// overlaps *or* touches.
+	///
+	/// See [`RangeBoundsMap::insert_merge_touching_or_overlapping()`]
+	/// for the full semantics.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut set = RangeBoundsSet::new();
+	/// set.insert(0..5).unwrap();
+	/// set.insert(10..15).unwrap();
+	///
+	/// assert_eq!(
+	/// 	set.insert_merge_touching_or_overlapping(3..12),
+	/// 	Ok(0..15)
+	/// );
+	/// ```
+	pub fn insert_merge_touching_or_overlapping(
+		&mut self,
+		range: K,
+	) -> Result<K, InsertError>
+	where
+		I: StepLite,
+	{
+		self.inner.insert_merge_touching_or_overlapping(range, ())
+	}
+
+	/// Inserts `range` unconditionally, trimming or removing any
+	/// existing ranges it overlaps to make room for it.
+	///
+	/// See [`RangeBoundsMap::insert_overwrite()`] for the full
+	/// semantics.
+	///
+	/// # Errors
+	///
+	/// Returns [`InsertError::StartAfterEnd`] if `range`'s
+	/// `start_bound()` is strictly after its `end_bound()`, leaving the
+	/// set unchanged.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut set = RangeBoundsSet::new();
+	/// set.insert(0..10).unwrap();
+	///
+	/// assert_eq!(set.insert_overwrite(4..6), Ok(vec![0..10]));
+	/// assert_eq!(set.iter().collect::<Vec<_>>(), [&(0..4), &(4..6), &(6..10)]);
+	/// ```
+	pub fn insert_overwrite(
+		&mut self,
+		range: K,
+	) -> Result<Vec<K>, InsertError>
+	where
+		I: StepLite,
+	{
+		Ok(self
+			.inner
+			.insert_overwrite(range, ())?
+			.into_iter()
+			.map(|(k, ())| k)
+			.collect())
+	}
+}
+
+impl<I, K> RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	/// Returns an iterator over the ranges covered by `self` *or*
+	/// `other`, in ascending order, with touching pieces coalesced into
+	/// maximal ranges.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut a = RangeBoundsSet::new();
+	/// a.insert(0..5).unwrap();
+	/// let mut b = RangeBoundsSet::new();
+	/// b.insert(3..8).unwrap();
+	///
+	/// assert_eq!(a.union(&b).collect::<Vec<_>>(), [0..8]);
+	/// ```
+	pub fn union<'a>(&'a self, other: &'a Self) -> SetOp<'a, I, K> {
+		SetOp::new(SetOpKind::Union, self, other)
+	}
+
+	/// Returns an iterator over the ranges covered by both `self` *and*
+	/// `other`, in ascending order.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut a = RangeBoundsSet::new();
+	/// a.insert(0..5).unwrap();
+	/// let mut b = RangeBoundsSet::new();
+	/// b.insert(3..8).unwrap();
+	///
+	/// assert_eq!(a.intersection(&b).collect::<Vec<_>>(), [3..5]);
+	/// ```
+	pub fn intersection<'a>(&'a self, other: &'a Self) -> SetOp<'a, I, K> {
+		SetOp::new(SetOpKind::Intersection, self, other)
+	}
+
+	/// Returns an iterator over the ranges covered by `self` but *not*
+	/// `other`, in ascending order.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut a = RangeBoundsSet::new();
+	/// a.insert(0..5).unwrap();
+	/// let mut b = RangeBoundsSet::new();
+	/// b.insert(3..8).unwrap();
+	///
+	/// assert_eq!(a.difference(&b).collect::<Vec<_>>(), [0..3]);
+	/// ```
+	pub fn difference<'a>(&'a self, other: &'a Self) -> SetOp<'a, I, K> {
+		SetOp::new(SetOpKind::Difference, self, other)
+	}
+
+	/// Returns an iterator over the ranges covered by exactly one of
+	/// `self` and `other`, in ascending order.
+	///
+	/// ```
+	/// use range_bounds_map::RangeBoundsSet;
+	///
+	/// let mut a = RangeBoundsSet::new();
+	/// a.insert(0..5).unwrap();
+	/// let mut b = RangeBoundsSet::new();
+	/// b.insert(3..8).unwrap();
+	///
+	/// assert_eq!(
+	/// 	a.symmetric_difference(&b).collect::<Vec<_>>(),
+	/// 	[0..3, 5..8]
+	/// );
+	/// ```
+	pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SetOp<'a, I, K> {
+		SetOp::new(SetOpKind::SymmetricDifference, self, other)
+	}
+
+	/// Returns `true` if every range in `self` is covered by `other`.
+	pub fn is_subset(&self, other: &Self) -> bool {
+		self.difference(other).next().is_none()
+	}
+
+	/// Returns `true` if every range in `other` is covered by `self`.
+	pub fn is_superset(&self, other: &Self) -> bool {
+		other.is_subset(self)
+	}
+
+	/// Returns `true` if `self` and `other` share no covered points.
+	pub fn is_disjoint(&self, other: &Self) -> bool {
+		self.intersection(other).next().is_none()
+	}
+}
+
+/// Which set-algebra operation a [`SetOp`] is computing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetOpKind {
+	Union,
+	Intersection,
+	Difference,
+	SymmetricDifference,
+}
+
+impl SetOpKind {
+	fn covers(self, in_a: bool, in_b: bool) -> bool {
+		match self {
+			SetOpKind::Union => in_a || in_b,
+			SetOpKind::Intersection => in_a && in_b,
+			SetOpKind::Difference => in_a && !in_b,
+			SetOpKind::SymmetricDifference => in_a != in_b,
+		}
+	}
+}
+
+/// A lazy iterator over the result of a set-algebra operation between
+/// two [`RangeBoundsSet`]s, created by [`union()`], [`intersection()`],
+/// [`difference()`] or [`symmetric_difference()`].
+///
+/// Works by walking both sets' sorted ranges simultaneously (like
+/// merging two sorted lists), tracking which of the two sets covers
+/// the current sweep position, and coalescing consecutive touching
+/// pieces that the operation decides to emit into a single output
+/// range.
+///
+/// [`union()`]: RangeBoundsSet::union
+/// [`intersection()`]: RangeBoundsSet::intersection
+/// [`difference()`]: RangeBoundsSet::difference
+/// [`symmetric_difference()`]: RangeBoundsSet::symmetric_difference
+pub struct SetOp<'a, I, K> {
+	kind: SetOpKind,
+	a: Peekable<Box<dyn Iterator<Item = &'a K> + 'a>>,
+	b: Peekable<Box<dyn Iterator<Item = &'a K> + 'a>>,
+	cursor: Bound<I>,
+	done: bool,
+}
+
+impl<'a, I, K> SetOp<'a, I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	fn new(
+		kind: SetOpKind,
+		a: &'a RangeBoundsSet<I, K>,
+		b: &'a RangeBoundsSet<I, K>,
+	) -> Self {
+		SetOp {
+			kind,
+			a: (Box::new(a.iter()) as Box<dyn Iterator<Item = &'a K> + 'a>)
+				.peekable(),
+			b: (Box::new(b.iter()) as Box<dyn Iterator<Item = &'a K> + 'a>)
+				.peekable(),
+			cursor: Bound::Unbounded,
+			done: false,
+		}
+	}
+}
+
+impl<'a, I, K> SetOp<'a, I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I>,
+{
+	/// Drops any front elements of `iter` that end at or before
+	/// `cursor`, since they can no longer affect anything from here on.
+	fn advance_past(
+		iter: &mut Peekable<Box<dyn Iterator<Item = &'a K> + 'a>>,
+		cursor: &Bound<I>,
+	) {
+		while let Some(k) = iter.peek() {
+			if ends_before_starts(&k.end_bound().cloned(), cursor) {
+				iter.next();
+			} else {
+				break;
+			}
+		}
+	}
+
+	/// Returns `true` if `iter`'s front element (if any, and assuming
+	/// [`advance_past`](Self::advance_past) has already been called)
+	/// has already started covering `cursor`.
+	fn peek_contains(
+		iter: &mut Peekable<Box<dyn Iterator<Item = &'a K> + 'a>>,
+		cursor: &Bound<I>,
+	) -> bool {
+		match iter.peek() {
+			Some(k) => {
+				StartBound(k.start_bound().cloned())
+					<= StartBound(cursor.clone())
+			}
+			None => false,
+		}
+	}
+
+	/// Returns the next position at which `iter`'s coverage might
+	/// change: the end of its front element if `currently_in`, or the
+	/// position just before its front element starts otherwise.
+	fn next_event(
+		iter: &mut Peekable<Box<dyn Iterator<Item = &'a K> + 'a>>,
+		currently_in: bool,
+	) -> Bound<I> {
+		match iter.peek() {
+			Some(k) if currently_in => k.end_bound().cloned(),
+			Some(k) => flip_bound(k.start_bound().cloned()),
+			None => Bound::Unbounded,
+		}
+	}
+
+	/// Resolves the single next maximal segment from `self.cursor`,
+	/// advancing it past the segment's end, and reports whether the
+	/// operation's result covers that segment.
+	fn next_segment(&mut self) -> (Bound<I>, Bound<I>, bool) {
+		Self::advance_past(&mut self.a, &self.cursor);
+		Self::advance_past(&mut self.b, &self.cursor);
+
+		let in_a = Self::peek_contains(&mut self.a, &self.cursor);
+		let in_b = Self::peek_contains(&mut self.b, &self.cursor);
+
+		let a_event = Self::next_event(&mut self.a, in_a);
+		let b_event = Self::next_event(&mut self.b, in_b);
+
+		let segment_start = self.cursor.clone();
+		let segment_end = if EndBound(a_event.clone()) <= EndBound(b_event.clone())
+		{
+			a_event
+		} else {
+			b_event
+		};
+
+		self.cursor = flip_bound(segment_end.clone());
+
+		(segment_start, segment_end, self.kind.covers(in_a, in_b))
+	}
+}
+
+impl<'a, I, K> Iterator for SetOp<'a, I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I>,
+{
+	type Item = K;
+
+	fn next(&mut self) -> Option<K> {
+		if self.done {
+			return None;
+		}
+
+		loop {
+			let (start, mut end, covers) = self.next_segment();
+
+			if matches!(self.cursor, Bound::Unbounded) {
+				self.done = true;
+			}
+
+			if covers {
+				while !self.done {
+					let resume_cursor = self.cursor.clone();
+					let (_, next_end, next_covers) = self.next_segment();
+
+					if !next_covers {
+						self.cursor = resume_cursor;
+						break;
+					}
+
+					end = next_end;
+					if matches!(self.cursor, Bound::Unbounded) {
+						self.done = true;
+					}
+				}
+
+				return Some(K::from_bounds(start, end));
+			}
+
+			if self.done {
+				return None;
+			}
+		}
+	}
+}
+
+impl<I, K> BitOr<&RangeBoundsSet<I, K>> for &RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	type Output = RangeBoundsSet<I, K>;
+
+	fn bitor(self, rhs: &RangeBoundsSet<I, K>) -> RangeBoundsSet<I, K> {
+		collect_into_set(self.union(rhs))
+	}
+}
+
+impl<I, K> BitAnd<&RangeBoundsSet<I, K>> for &RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	type Output = RangeBoundsSet<I, K>;
+
+	fn bitand(self, rhs: &RangeBoundsSet<I, K>) -> RangeBoundsSet<I, K> {
+		collect_into_set(self.intersection(rhs))
+	}
+}
+
+impl<I, K> Sub<&RangeBoundsSet<I, K>> for &RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	type Output = RangeBoundsSet<I, K>;
+
+	fn sub(self, rhs: &RangeBoundsSet<I, K>) -> RangeBoundsSet<I, K> {
+		collect_into_set(self.difference(rhs))
+	}
+}
+
+impl<I, K> BitXor<&RangeBoundsSet<I, K>> for &RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + RangeBoundsExt<I> + Clone,
+{
+	type Output = RangeBoundsSet<I, K>;
+
+	fn bitxor(self, rhs: &RangeBoundsSet<I, K>) -> RangeBoundsSet<I, K> {
+		collect_into_set(self.symmetric_difference(rhs))
+	}
+}
+
+/// Inserts every range yielded by `ranges` into a fresh
+/// `RangeBoundsSet`.
+///
+/// Only used by the `BitOr`/`BitAnd`/`Sub`/`BitXor` impls, whose source
+/// iterators always yield non-overlapping ranges by construction, so
+/// the `insert()` here can never fail.
+fn collect_into_set<I, K>(
+	ranges: impl Iterator<Item = K>,
+) -> RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone,
+{
+	let mut set = RangeBoundsSet::new();
+	for range in ranges {
+		set.insert(range).expect(
+			"set algebra result ranges must not overlap",
+		);
+	}
+	set
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ops::Range;
+
+	use super::*;
+
+	fn set(ranges: impl IntoIterator<Item = Range<i32>>) -> RangeBoundsSet<i32, Range<i32>> {
+		let mut set = RangeBoundsSet::new();
+		for range in ranges {
+			set.insert(range).unwrap();
+		}
+		set
+	}
+
+	#[test]
+	fn union_coalesces_touching_pieces_from_both_sets() {
+		let a = set(vec![0..5, 10..15]);
+		let b = set(vec![5..10, 20..25]);
+
+		assert_eq!(
+			a.union(&b).collect::<Vec<_>>(),
+			[0..15, 20..25]
+		);
+	}
+
+	#[test]
+	fn intersection_of_disjoint_sets_is_empty() {
+		let a = set(std::iter::once(0..5));
+		let b = set(std::iter::once(5..10));
+
+		assert_eq!(a.intersection(&b).collect::<Vec<_>>(), Vec::<Range<i32>>::new());
+	}
+
+	#[test]
+	fn difference_is_not_symmetric() {
+		let a = set(std::iter::once(0..10));
+		let b = set(std::iter::once(3..5));
+
+		assert_eq!(a.difference(&b).collect::<Vec<_>>(), [0..3, 5..10]);
+		assert_eq!(b.difference(&a).collect::<Vec<_>>(), Vec::<Range<i32>>::new());
+	}
+
+	#[test]
+	fn symmetric_difference_with_an_empty_set_is_the_other_set() {
+		let a = set(vec![0..5, 10..15]);
+		let b: RangeBoundsSet<i32, Range<i32>> = RangeBoundsSet::new();
+
+		assert_eq!(
+			a.symmetric_difference(&b).collect::<Vec<_>>(),
+			[0..5, 10..15]
+		);
+	}
+
+	#[test]
+	fn is_subset_is_superset_and_is_disjoint() {
+		let a = set(std::iter::once(2..4));
+		let b = set(std::iter::once(0..10));
+		let c = set(std::iter::once(20..25));
+
+		assert!(a.is_subset(&b));
+		assert!(!b.is_subset(&a));
+		assert!(b.is_superset(&a));
+		assert!(a.is_disjoint(&c));
+		assert!(!a.is_disjoint(&b));
+	}
+
+	#[test]
+	fn bitwise_operators_match_their_named_methods() {
+		let a = set(vec![0..5, 10..15]);
+		let b = set(std::iter::once(3..12));
+
+		let union: Vec<Range<i32>> = (&a | &b).iter().cloned().collect();
+		assert_eq!(union, a.union(&b).collect::<Vec<_>>());
+
+		let intersection: Vec<Range<i32>> = (&a & &b).iter().cloned().collect();
+		assert_eq!(intersection, a.intersection(&b).collect::<Vec<_>>());
+
+		let difference: Vec<Range<i32>> = (&a - &b).iter().cloned().collect();
+		assert_eq!(difference, a.difference(&b).collect::<Vec<_>>());
+
+		let symmetric_difference: Vec<Range<i32>> =
+			(&a ^ &b).iter().cloned().collect();
+		assert_eq!(
+			symmetric_difference,
+			a.symmetric_difference(&b).collect::<Vec<_>>()
+		);
+	}
+}
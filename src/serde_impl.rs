@@ -0,0 +1,225 @@
+/*
+Copyright 2022 James Forster
+
+This file is part of range_bounds_map.
+
+range_bounds_map is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+range_bounds_map is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with range_bounds_map. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! [`Serialize`]/[`Deserialize`] impls for [`RangeBoundsMap`] and
+//! [`RangeBoundsSet`], behind the `serde` feature flag.
+//!
+//! Both are serialized as a sequence of `(range, value)` pairs (just
+//! `range` for the set), in the same order [`iter()`] yields them.
+//! Deserializing goes back through the normal validating `insert()`,
+//! so overlapping or otherwise malformed input produces a serde error
+//! rather than a silently-corrupt map.
+//!
+//! [`iter()`]: crate::RangeBoundsMap::iter
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+
+use serde::de::{Deserialize, Deserializer, Error as DeError, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::range_bounds_map::{InsertError, RangeBoundsMap};
+use crate::range_bounds_set::RangeBoundsSet;
+
+impl<I, K, V> Serialize for RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone + Serialize,
+	V: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut seq = serializer.serialize_seq(Some(self.len()))?;
+		for pair in self.iter() {
+			seq.serialize_element(&pair)?;
+		}
+		seq.end()
+	}
+}
+
+impl<'de, I, K, V> Deserialize<'de> for RangeBoundsMap<I, K, V>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone + Deserialize<'de>,
+	V: Deserialize<'de>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		struct RangeBoundsMapVisitor<I, K, V> {
+			marker: PhantomData<(I, K, V)>,
+		}
+
+		impl<'de, I, K, V> Visitor<'de> for RangeBoundsMapVisitor<I, K, V>
+		where
+			I: Ord + Clone,
+			K: RangeBounds<I> + Clone + Deserialize<'de>,
+			V: Deserialize<'de>,
+		{
+			type Value = RangeBoundsMap<I, K, V>;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a sequence of non-overlapping (range, value) pairs")
+			}
+
+			fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+			where
+				A: SeqAccess<'de>,
+			{
+				let mut map = RangeBoundsMap::new();
+
+				while let Some((range, value)) = seq.next_element()? {
+					map.insert(range, value).map_err(|err| {
+						A::Error::custom(match err {
+							InsertError::OverlapsExistingEntry => {
+								"RangeBoundsMap entries must not overlap"
+							}
+							InsertError::StartAfterEnd => {
+								"RangeBoundsMap entries must not start after they end"
+							}
+						})
+					})?;
+				}
+
+				Ok(map)
+			}
+		}
+
+		deserializer.deserialize_seq(RangeBoundsMapVisitor {
+			marker: PhantomData,
+		})
+	}
+}
+
+impl<I, K> Serialize for RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone + Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut seq = serializer.serialize_seq(Some(self.len()))?;
+		for range in self.iter() {
+			seq.serialize_element(range)?;
+		}
+		seq.end()
+	}
+}
+
+impl<'de, I, K> Deserialize<'de> for RangeBoundsSet<I, K>
+where
+	I: Ord + Clone,
+	K: RangeBounds<I> + Clone + Deserialize<'de>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		struct RangeBoundsSetVisitor<I, K> {
+			marker: PhantomData<(I, K)>,
+		}
+
+		impl<'de, I, K> Visitor<'de> for RangeBoundsSetVisitor<I, K>
+		where
+			I: Ord + Clone,
+			K: RangeBounds<I> + Clone + Deserialize<'de>,
+		{
+			type Value = RangeBoundsSet<I, K>;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a sequence of non-overlapping ranges")
+			}
+
+			fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+			where
+				A: SeqAccess<'de>,
+			{
+				let mut set = RangeBoundsSet::new();
+
+				while let Some(range) = seq.next_element()? {
+					set.insert(range).map_err(|err| {
+						A::Error::custom(match err {
+							InsertError::OverlapsExistingEntry => {
+								"RangeBoundsSet entries must not overlap"
+							}
+							InsertError::StartAfterEnd => {
+								"RangeBoundsSet entries must not start after they end"
+							}
+						})
+					})?;
+				}
+
+				Ok(set)
+			}
+		}
+
+		deserializer.deserialize_seq(RangeBoundsSetVisitor {
+			marker: PhantomData,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn deserialize_round_trips_a_map() {
+		let mut map = RangeBoundsMap::new();
+		map.insert(0..5, true).unwrap();
+		map.insert(10..15, false).unwrap();
+
+		let json = serde_json::to_string(&map).unwrap();
+		let round_tripped: RangeBoundsMap<i32, std::ops::Range<i32>, bool> =
+			serde_json::from_str(&json).unwrap();
+
+		assert_eq!(
+			round_tripped.iter().collect::<Vec<_>>(),
+			map.iter().collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn deserialize_rejects_overlapping_entries() {
+		let result: Result<
+			RangeBoundsMap<i32, std::ops::Range<i32>, bool>,
+			_,
+		> = serde_json::from_str(
+			r#"[[{"start":0,"end":5},true],[{"start":3,"end":8},false]]"#,
+		);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn deserialize_rejects_a_backwards_range() {
+		type BackwardsKey = (std::ops::Bound<i32>, std::ops::Bound<i32>);
+
+		let result: Result<RangeBoundsMap<i32, BackwardsKey, bool>, _> =
+			serde_json::from_str(r#"[[[{"Included":10},{"Included":5}],true]]"#);
+
+		assert!(result.is_err());
+	}
+}